@@ -0,0 +1,35 @@
+use crate::evdev::{Device, Event};
+use std::io;
+use tokio::io::unix::AsyncFd;
+
+/// A `Device` driven by fd readiness instead of a dedicated blocking read
+/// thread, so it can be `select!`ed alongside other async sources (e.g.
+/// stdin) in a single task.
+pub struct AsyncDevice {
+    inner: AsyncFd<Device>,
+}
+
+impl AsyncDevice {
+    pub fn new(dev: Device) -> io::Result<Self> {
+        dev.set_nonblocking(true)?;
+        Ok(Self {
+            inner: AsyncFd::new(dev)?,
+        })
+    }
+
+    pub async fn next_event(&mut self) -> io::Result<Event> {
+        loop {
+            let mut guard = self.inner.readable_mut().await?;
+            let result = guard.try_io(|dev| match dev.get_mut().next_event_nonblocking() {
+                Ok(Some(event)) => Ok(event),
+                Ok(None) => Err(io::Error::from(io::ErrorKind::WouldBlock)),
+                Err(err) => Err(err),
+            });
+            match result {
+                Ok(result) => return result,
+                // Spurious wakeup; clear readiness and wait again.
+                Err(_would_block) => continue,
+            }
+        }
+    }
+}
@@ -3,11 +3,12 @@
 use evdev_sys as sys;
 use glob::glob;
 use std::{
-    ffi::CStr,
+    collections::VecDeque,
+    ffi::{CStr, CString},
     fs::File,
     io,
     os::{raw::c_uint, unix::io::AsRawFd},
-    path::Path,
+    path::{Path, PathBuf},
     ptr,
 };
 
@@ -25,8 +26,12 @@ macro_rules! unsafe_io {
 pub struct Device {
     _file: File,
     raw: *mut sys::libevdev,
+    path: PathBuf,
     name: String,
     grabbed: bool,
+    // Extra events yielded by a resync (see `resync`) that haven't been
+    // returned to the caller yet.
+    pending: VecDeque<Event>,
 }
 
 impl Device {
@@ -36,8 +41,16 @@ impl Device {
             .filter_map(|path| Some(Device::open(path.ok()?).ok()?))
     }
 
+    /// Finds the first device whose `name()` matches exactly.
+    pub fn with_name(name: &str) -> io::Result<Self> {
+        Device::list()
+            .find(|dev| dev.name() == name)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("No such device: {}", name)))
+    }
+
     pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
-        let file = File::open(path)?;
+        let path = path.as_ref().to_owned();
+        let file = File::open(&path)?;
 
         let mut raw = ptr::null_mut();
         unsafe_io!(sys::libevdev_new_from_fd(file.as_raw_fd(), &mut raw));
@@ -51,11 +64,17 @@ impl Device {
         Ok(Self {
             _file: file,
             raw,
+            path,
             name,
             grabbed: false,
+            pending: VecDeque::new(),
         })
     }
 
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -80,27 +99,66 @@ impl Device {
     pub fn read_loop(&mut self) -> DeviceReadLoop {
         DeviceReadLoop(self)
     }
-}
 
-unsafe impl Send for Device {}
+    /// Puts the underlying fd in non-blocking mode, so `next_event_nonblocking`
+    /// can be driven from a readiness-based event loop instead of a dedicated
+    /// blocking read thread (see `async_evdev`).
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        let fd = self._file.as_raw_fd();
+        let flags = unsafe { libc::fcntl(fd, libc::F_GETFL, 0) };
+        if flags < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let flags = if nonblocking {
+            flags | libc::O_NONBLOCK
+        } else {
+            flags & !libc::O_NONBLOCK
+        };
+        if unsafe { libc::fcntl(fd, libc::F_SETFL, flags) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
 
-impl Drop for Device {
-    fn drop(&mut self) {
-        if self.grabbed {
-            self.ungrab().unwrap();
+    /// Blocks until the next event is available.
+    pub fn next_event(&mut self) -> io::Result<Event> {
+        if let Some(event) = self.pending.pop_front() {
+            return Ok(event);
         }
-        unsafe {
-            sys::libevdev_free(self.raw);
+
+        let flags = (sys::LIBEVDEV_READ_FLAG_NORMAL | sys::LIBEVDEV_READ_FLAG_BLOCKING) as c_uint;
+        self.next_event_with_flags(flags)
+    }
+
+    /// Like `next_event`, but returns `Ok(None)` instead of blocking when no
+    /// event is ready yet. Requires `set_nonblocking(true)` to have been
+    /// called first.
+    pub fn next_event_nonblocking(&mut self) -> io::Result<Option<Event>> {
+        if let Some(event) = self.pending.pop_front() {
+            return Ok(Some(event));
+        }
+
+        match self.next_event_with_flags(sys::LIBEVDEV_READ_FLAG_NORMAL as c_uint) {
+            Ok(event) => Ok(Some(event)),
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => Ok(None),
+            Err(err) => Err(err),
         }
     }
-}
 
-pub struct DeviceReadLoop<'a>(&'a mut Device);
+    fn next_event_with_flags(&mut self, flags: c_uint) -> io::Result<Event> {
+        let status = self.next_raw(flags);
 
-impl<'a> Iterator for DeviceReadLoop<'a> {
-    type Item = Event;
+        match status as u32 {
+            sys::LIBEVDEV_READ_STATUS_SUCCESS => Ok(self.pending.pop_front().unwrap()),
+            sys::LIBEVDEV_READ_STATUS_SYNC => {
+                self.resync()?;
+                Ok(self.pending.pop_front().unwrap())
+            }
+            _ => Err(io::Error::from_raw_os_error(-status)),
+        }
+    }
 
-    fn next(&mut self) -> Option<Event> {
+    fn next_raw(&mut self, flags: c_uint) -> i32 {
         let mut ev = sys::input_event {
             time: sys::timeval {
                 tv_sec: 0,
@@ -110,14 +168,60 @@ impl<'a> Iterator for DeviceReadLoop<'a> {
             code: 0,
             value: 0,
         };
+        let status = unsafe { sys::libevdev_next_event(self.raw, flags, &mut ev) };
+        if status >= 0 {
+            self.pending.push_back(ev.into());
+        }
+        status
+    }
+
+    // Drains a SYN_DROPPED overflow by repeatedly asking libevdev for the
+    // synthetic events needed to bring our view of the device state back in
+    // sync, stopping once it reports there's nothing more to catch up on.
+    fn resync(&mut self) -> io::Result<()> {
+        loop {
+            let status = self.next_raw(sys::LIBEVDEV_READ_FLAG_SYNC as c_uint);
+            if status == sys::LIBEVDEV_READ_STATUS_SYNC as i32 {
+                continue;
+            }
+
+            let err = io::Error::from_raw_os_error(-status);
+            if err.kind() == io::ErrorKind::WouldBlock {
+                return Ok(());
+            }
+            return Err(err);
+        }
+    }
+}
+
+impl AsRawFd for Device {
+    fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
+        self._file.as_raw_fd()
+    }
+}
+
+unsafe impl Send for Device {}
+
+impl Drop for Device {
+    fn drop(&mut self) {
+        if self.grabbed {
+            // Ignore errors: if the device has been unplugged the fd is
+            // already dead and ungrab is expected to fail (e.g. ENODEV).
+            let _ = self.ungrab();
+        }
         unsafe {
-            sys::libevdev_next_event(
-                self.0.raw,
-                (sys::LIBEVDEV_READ_FLAG_NORMAL | sys::LIBEVDEV_READ_FLAG_BLOCKING) as c_uint,
-                &mut ev,
-            );
+            sys::libevdev_free(self.raw);
         }
-        Some(ev.into())
+    }
+}
+
+pub struct DeviceReadLoop<'a>(&'a mut Device);
+
+impl<'a> Iterator for DeviceReadLoop<'a> {
+    type Item = io::Result<Event>;
+
+    fn next(&mut self) -> Option<io::Result<Event>> {
+        Some(self.0.next_event())
     }
 }
 
@@ -137,6 +241,26 @@ impl UInput {
         Ok(Self { raw })
     }
 
+    /// Like `from_device`, but the resulting uinput device supports the
+    /// union of every device's event types/codes, rather than just the
+    /// first's. Needed when several physical devices (e.g. a steno machine
+    /// and a regular keyboard) with different key sets are merged into one
+    /// output stream: writing an event the uinput device wasn't created
+    /// with fails, so a capability set of just one of the devices would
+    /// drop (or error on) keys from the others.
+    pub fn from_devices<'a>(devices: impl IntoIterator<Item = &'a Device>) -> io::Result<Self> {
+        let union = UnionDevice::new(devices)?;
+
+        let mut raw = ptr::null_mut();
+        unsafe_io!(sys::libevdev_uinput_create_from_device(
+            union.0,
+            sys::LIBEVDEV_UINPUT_OPEN_MANAGED,
+            &mut raw
+        ));
+
+        Ok(Self { raw })
+    }
+
     pub fn write_event(&mut self, event: Event) -> io::Result<()> {
         let ev: sys::input_event = event.into();
         unsafe_io!(sys::libevdev_uinput_write_event(
@@ -163,6 +287,62 @@ impl Drop for UInput {
     }
 }
 
+// A scratch `libevdev` context (never backed by a real fd) used purely to
+// accumulate the union of capabilities across several devices before handing
+// it to `libevdev_uinput_create_from_device`; capabilities can't be added to
+// a uinput device after it's created, so the union has to be built first.
+struct UnionDevice(*mut sys::libevdev);
+
+impl UnionDevice {
+    fn new<'a>(devices: impl IntoIterator<Item = &'a Device>) -> io::Result<Self> {
+        let raw = unsafe { sys::libevdev_new() };
+        if raw.is_null() {
+            return Err(io::Error::new(io::ErrorKind::Other, "libevdev_new failed"));
+        }
+        let union = Self(raw);
+
+        for (i, dev) in devices.into_iter().enumerate() {
+            if i == 0 {
+                let name = CString::new(dev.name()).unwrap();
+                unsafe { sys::libevdev_set_name(union.0, name.as_ptr()) };
+            }
+
+            for type_ in 0..=EV_MAX as c_uint {
+                if unsafe { sys::libevdev_has_event_type(dev.raw, type_) } == 0 {
+                    continue;
+                }
+                unsafe_io!(sys::libevdev_enable_event_type(union.0, type_));
+
+                let max_code = unsafe { sys::libevdev_event_type_get_max(type_) };
+                if max_code < 0 {
+                    continue;
+                }
+                for code in 0..=max_code as c_uint {
+                    if unsafe { sys::libevdev_has_event_code(dev.raw, type_, code) } == 0 {
+                        continue;
+                    }
+                    unsafe_io!(sys::libevdev_enable_event_code(
+                        union.0,
+                        type_,
+                        code,
+                        ptr::null()
+                    ));
+                }
+            }
+        }
+
+        Ok(union)
+    }
+}
+
+impl Drop for UnionDevice {
+    fn drop(&mut self) {
+        unsafe {
+            sys::libevdev_free(self.0);
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum Event {
     Syn,
@@ -211,6 +391,9 @@ impl Into<sys::input_event> for Event {
 
 const EV_SYN: u16 = 0;
 const EV_KEY: u16 = 1;
+// Kernel ABI constant (linux/input-event-codes.h); stable, so it's fine to
+// hardcode like EV_SYN/EV_KEY above.
+const EV_MAX: u16 = 0x1f;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Key(pub u16);
@@ -0,0 +1,76 @@
+use std::{
+    io,
+    os::unix::io::AsRawFd,
+    path::PathBuf,
+    sync::mpsc,
+    thread,
+};
+use udev::{EventType, MonitorBuilder, MonitorSocket};
+
+pub enum HotplugEvent {
+    Added(PathBuf),
+    Removed(PathBuf),
+}
+
+// The monitor socket's fd is non-blocking, so `MonitorSocket::iter` returns
+// immediately rather than waiting for the next event; block on the fd
+// ourselves with `poll` in between drains.
+fn wait_readable(socket: &MonitorSocket) -> io::Result<()> {
+    let mut fd = libc::pollfd {
+        fd: socket.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let ret = unsafe { libc::poll(&mut fd, 1, -1) };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Watches the `input` subsystem for devices whose `NAME` udev property is
+/// one of `names` appearing or disappearing, and reports a `HotplugEvent`
+/// for each over the returned channel.
+pub fn watch(names: Vec<String>) -> mpsc::Receiver<HotplugEvent> {
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let socket = MonitorBuilder::new()
+            .unwrap()
+            .match_subsystem("input")
+            .unwrap()
+            .listen()
+            .unwrap();
+
+        while wait_readable(&socket).is_ok() {
+            for event in socket.iter() {
+                let Some(devnode) = event.device().devnode().map(PathBuf::from) else {
+                    continue;
+                };
+
+                let is_match = event
+                    .device()
+                    .property_value("NAME")
+                    .and_then(|name| name.to_str())
+                    .map_or(false, |name| names.iter().any(|n| n == name.trim_matches('"')));
+
+                if !is_match {
+                    continue;
+                }
+
+                let hotplug_event = match event.event_type() {
+                    EventType::Add => HotplugEvent::Added(devnode),
+                    EventType::Remove => HotplugEvent::Removed(devnode),
+                    _ => continue,
+                };
+
+                if tx.send(hotplug_event).is_err() {
+                    // The receiving end has gone away; nothing left to watch for.
+                    return;
+                }
+            }
+        }
+    });
+
+    rx
+}
@@ -1,10 +1,17 @@
+mod async_evdev;
 mod evdev;
+mod hotplug;
+mod remap;
 
+use async_evdev::AsyncDevice;
 use evdev::*;
+use hotplug::HotplugEvent;
+use remap::{RemapConfig, Remapper};
+use tokio::io::AsyncBufReadExt;
 use std::{
     collections::HashSet,
     io::{self, BufRead, Write},
-    sync::{Arc, Mutex},
+    sync::{mpsc, Arc, Mutex},
     thread,
     time::Duration,
 };
@@ -22,6 +29,20 @@ const MODIFIER_KEYS: [Key; 8] = [
     Key::KEY_RIGHTMETA,
 ];
 
+// Shared between `listen_kb` and `run_async` so the two loops can't drift
+// apart on what counts as a held modifier.
+fn track_modifiers(modifiers: &mut Vec<Key>, key: Key, pressed: bool) {
+    if MODIFIER_KEYS.contains(&key) {
+        if pressed {
+            if !modifiers.contains(&key) {
+                modifiers.push(key);
+            }
+        } else {
+            modifiers.retain(|k| k != &key);
+        }
+    }
+}
+
 fn is_keyboard(dev: &Device) -> bool {
     dev.name() != UINPUT_NAME && dev.has_key(Key::KEY_A)
 }
@@ -32,31 +53,79 @@ fn find_first_keyboard() -> Device {
         .expect("No keyboards found")
 }
 
-fn listen_kb(mut dev: Device, uinput: Arc<Mutex<UInput>>, suppress_keys: Arc<Mutex<HashSet<Key>>>) {
-    let stdout = io::stdout();
-    // stdout is only used in this method, so we may as well only lock it once
-    let mut stdout = stdout.lock();
+fn list_devices() {
+    for dev in Device::list() {
+        println!("{}\t{}", dev.path().display(), dev.name());
+    }
+}
+
+// Raw device events are funnelled through this channel alongside dual-role
+// hold timeouts from the remap layer, so a single consumer loop can react to
+// either without a select over two different blocking sources.
+enum KbMsg {
+    Event(io::Result<Event>),
+    HoldTimeout(remap::HoldTimeout),
+}
+
+fn listen_kb(
+    mut dev: Device,
+    uinput: Arc<Mutex<UInput>>,
+    suppress_keys: Arc<Mutex<HashSet<Key>>>,
+    report_tx: mpsc::Sender<String>,
+    remap_config: Arc<RemapConfig>,
+) {
+    let (msg_tx, msg_rx) = mpsc::channel();
+
+    let event_tx = msg_tx.clone();
+    thread::spawn(move || {
+        for event in dev.read_loop() {
+            let is_err = event.is_err();
+            if event_tx.send(KbMsg::Event(event)).is_err() || is_err {
+                // Either the consumer is gone, or the device itself is
+                // (unplugged, or an unrecoverable read error): either way
+                // there's nothing left to read.
+                break;
+            }
+        }
+    });
 
     let mut modifiers = Vec::new();
+    let mut remapper = Remapper::new(&remap_config, move |timeout| {
+        let _ = msg_tx.send(KbMsg::HoldTimeout(timeout));
+    });
 
-    for event in dev.read_loop() {
-        if let Event::Key(key, pressed) = event {
-            if MODIFIER_KEYS.contains(&key) {
-                if pressed {
-                    if modifiers.contains(&key) {
-                        modifiers.push(key);
-                    }
-                } else {
-                    modifiers.retain(|k| k != &key);
-                }
+    for msg in msg_rx {
+        let event = match msg {
+            KbMsg::Event(Ok(event)) => event,
+            // The device was unplugged, or some other unrecoverable error
+            // occurred; stop listening to it.
+            KbMsg::Event(Err(_)) => break,
+            KbMsg::HoldTimeout(timeout) => {
+                remapper.on_hold_timeout(timeout, |key, pressed| {
+                    let mut uinput = uinput.lock().unwrap();
+                    uinput.write_event(Event::Key(key, pressed)).unwrap();
+                });
+                continue;
             }
+        };
+
+        if let Event::Key(key, pressed) = event {
+            track_modifiers(&mut modifiers, key, pressed);
 
             if (*suppress_keys.lock().unwrap()).contains(&key) && modifiers.len() == 0 {
                 let Key(code) = key;
                 let prefix = if pressed { 'd' } else { 'u' };
-                writeln!(stdout, "{}{}", prefix, code).unwrap();
+                // Forward through the shared channel rather than writing stdout
+                // directly, since several keyboards may be grabbed at once.
+                report_tx.send(format!("{}{}", prefix, code)).unwrap();
                 continue;
             }
+
+            remapper.on_key(key, pressed, |key, pressed| {
+                let mut uinput = uinput.lock().unwrap();
+                uinput.write_event(Event::Key(key, pressed)).unwrap();
+            });
+            continue;
         }
 
         {
@@ -66,6 +135,146 @@ fn listen_kb(mut dev: Device, uinput: Arc<Mutex<UInput>>, suppress_keys: Arc<Mut
     }
 }
 
+fn spawn_listen_kb(
+    mut dev: Device,
+    uinput: Arc<Mutex<UInput>>,
+    suppress_keys: Arc<Mutex<HashSet<Key>>>,
+    report_tx: mpsc::Sender<String>,
+    remap_config: Arc<RemapConfig>,
+) -> io::Result<()> {
+    dev.grab()?;
+    eprintln!("Using device: {}", dev.name());
+    thread::spawn(move || listen_kb(dev, uinput, suppress_keys, report_tx, remap_config));
+    Ok(())
+}
+
+// Watches for add/remove events on the configured devices so a steno machine
+// can be unplugged and replugged without restarting Plover. Teardown of a
+// removed device's thread and grab happens on its own: `listen_kb` exits as
+// soon as its `read_loop` reports the device is gone (see evdev::DeviceReadLoop).
+//
+// Deliberately reuses the single startup `uinput` rather than building a
+// fresh `UInput::from_device` for each reconnected device: this crate merges
+// every configured keyboard into one virtual output device, and `names` is
+// expected to identify the same (or an identically-capable) physical
+// keyboard on replug, so the original capability set still applies.
+fn spawn_hotplug_watcher(
+    names: Vec<String>,
+    uinput: Arc<Mutex<UInput>>,
+    suppress_keys: Arc<Mutex<HashSet<Key>>>,
+    report_tx: mpsc::Sender<String>,
+    remap_config: Arc<RemapConfig>,
+) {
+    let hotplug_rx = hotplug::watch(names);
+
+    thread::spawn(move || {
+        for event in hotplug_rx {
+            if let HotplugEvent::Added(path) = event {
+                let dev = match Device::open(&path) {
+                    Ok(dev) => dev,
+                    Err(_) => continue,
+                };
+                let name = dev.name().to_owned();
+                // A freshly-added device can transiently fail to grab (e.g.
+                // udev is still applying permissions right after the `add`
+                // uevent); log and drop it rather than panicking this
+                // thread, which would silently kill hotplug watching for
+                // the rest of the run.
+                if let Err(err) = spawn_listen_kb(
+                    dev,
+                    uinput.clone(),
+                    suppress_keys.clone(),
+                    report_tx.clone(),
+                    remap_config.clone(),
+                ) {
+                    eprintln!("Failed to grab hotplugged device {}: {}", name, err);
+                }
+            }
+        }
+    });
+}
+
+// Async alternative to the `listen_kb`/`listen_stdio` thread pair: both the
+// keyboard and stdin are driven off fd readiness in one task, so there's no
+// `Arc<Mutex<UInput>>` contention and no need for the 500ms startup sleep.
+//
+// Scope limitation: this path handles exactly one device and does not run
+// the remap layer, merge multiple keyboards, or watch for hotplug; it's a
+// minimal single-device loop, not a drop-in replacement for the
+// `listen_kb`/`spawn_hotplug_watcher` pipeline.
+async fn run_async(mut dev: Device) -> io::Result<()> {
+    dev.grab()?;
+    eprintln!("Using device: {}", dev.name());
+
+    let mut uinput = UInput::from_device(&dev)?;
+    let mut suppress_keys: HashSet<Key> = HashSet::new();
+    let mut modifiers = Vec::new();
+
+    let mut async_dev = AsyncDevice::new(dev)?;
+    let mut lines = tokio::io::BufReader::new(tokio::io::stdin()).lines();
+
+    loop {
+        tokio::select! {
+            event = async_dev.next_event() => {
+                let event = event?;
+
+                if let Event::Key(key, pressed) = event {
+                    track_modifiers(&mut modifiers, key, pressed);
+
+                    if suppress_keys.contains(&key) && modifiers.len() == 0 {
+                        let Key(code) = key;
+                        let prefix = if pressed { 'd' } else { 'u' };
+                        println!("{}{}", prefix, code);
+                        continue;
+                    }
+                }
+
+                uinput.write_event(event)?;
+            }
+            line = lines.next_line() => {
+                let Some(line) = line? else { break };
+                if let Some(first_char) = line.chars().next() {
+                    let content = &line[1..];
+                    match first_char {
+                        'd' => {
+                            if let Ok(code) = content.parse() {
+                                uinput.write_event(Event::Key(Key(code), true))?;
+                                uinput.syn()?;
+                            }
+                        }
+                        'u' => {
+                            if let Ok(code) = content.parse() {
+                                uinput.write_event(Event::Key(Key(code), false))?;
+                                uinput.syn()?;
+                            }
+                        }
+                        's' => {
+                            suppress_keys = content
+                                .split(' ')
+                                .filter_map(|substr| substr.parse().ok())
+                                .map(Key)
+                                .collect();
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn report_stdout(report_rx: mpsc::Receiver<String>) {
+    let stdout = io::stdout();
+    // stdout is only written to from this method, so we may as well only lock it once
+    let mut stdout = stdout.lock();
+
+    for line in report_rx {
+        writeln!(stdout, "{}", line).unwrap();
+    }
+}
+
 fn listen_stdio(uinput: Arc<Mutex<UInput>>, suppress_keys: Arc<Mutex<HashSet<Key>>>) {
     let stdin = io::stdin();
     // stdin is only used in this method, so we may as well only lock it once
@@ -111,19 +320,67 @@ fn listen_stdio(uinput: Arc<Mutex<UInput>>, suppress_keys: Arc<Mutex<HashSet<Key
 }
 
 fn main() {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.iter().any(|arg| arg == "--list-devices") {
+        list_devices();
+        return;
+    }
+
+    if let Some(pos) = args.iter().position(|arg| arg == "--async") {
+        args.remove(pos);
+        let dev = match args.first() {
+            Some(name) => Device::with_name(name).unwrap(),
+            None => find_first_keyboard(),
+        };
+        return tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(run_async(dev))
+            .unwrap();
+    }
+
+    let remap_config = Arc::new(match args.iter().position(|arg| arg == "--remap") {
+        Some(pos) => {
+            let path = args.get(pos + 1).expect("--remap requires a path").clone();
+            let config = RemapConfig::load(path.as_ref()).unwrap();
+            args.drain(pos..=pos + 1);
+            config
+        }
+        None => RemapConfig::default(),
+    });
+
     thread::sleep(Duration::from_millis(500));
 
-    let mut dev = find_first_keyboard();
-    dev.grab().unwrap();
-    eprintln!("Using device: {}", dev.name());
+    let devices: Vec<Device> = if args.is_empty() {
+        vec![find_first_keyboard()]
+    } else {
+        args.iter()
+            .map(|name| Device::with_name(name).unwrap())
+            .collect()
+    };
+    let names: Vec<String> = devices.iter().map(|dev| dev.name().to_owned()).collect();
 
-    let uinput = Arc::new(Mutex::new(UInput::from_device(&dev).unwrap()));
+    let uinput = Arc::new(Mutex::new(UInput::from_devices(&devices).unwrap()));
     let uinput_ = uinput.clone();
 
     let suppress_keys = Arc::new(Mutex::new(HashSet::new()));
     let suppress_keys_ = suppress_keys.clone();
 
-    thread::spawn(|| listen_kb(dev, uinput, suppress_keys));
+    let (report_tx, report_rx) = mpsc::channel();
+    thread::spawn(|| report_stdout(report_rx));
+
+    for dev in devices {
+        spawn_listen_kb(
+            dev,
+            uinput.clone(),
+            suppress_keys.clone(),
+            report_tx.clone(),
+            remap_config.clone(),
+        )
+        .unwrap();
+    }
+
+    spawn_hotplug_watcher(names, uinput, suppress_keys, report_tx, remap_config);
 
     thread::spawn(|| listen_stdio(uinput_, suppress_keys_))
         .join()
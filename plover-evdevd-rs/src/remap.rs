@@ -0,0 +1,330 @@
+use crate::evdev::Key;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    fs,
+    io,
+    path::Path,
+    thread,
+    time::Duration,
+};
+
+fn default_threshold_ms() -> u64 {
+    200
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum RemapEntry {
+    /// A plain one-to-one substitution.
+    Simple { from: u16, to: u16 },
+    /// A key that emits `tap` when pressed and released within
+    /// `threshold_ms`, or acts as `hold` (typically a modifier) if another
+    /// key is pressed first or it's still down once the threshold passes.
+    DualRole {
+        from: u16,
+        tap: u16,
+        hold: u16,
+        #[serde(default = "default_threshold_ms")]
+        threshold_ms: u64,
+    },
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RemapConfig {
+    #[serde(default, rename = "remap")]
+    entries: Vec<RemapEntry>,
+}
+
+impl RemapConfig {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        toml::from_str(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+/// Notifies a `Remapper` that a dual-role key's hold threshold has elapsed
+/// without being pre-empted by a tap or another keystroke.
+pub struct HoldTimeout {
+    from: u16,
+    generation: u64,
+}
+
+struct DualRole {
+    tap: u16,
+    hold: u16,
+    threshold_ms: u64,
+}
+
+// Tracks a dual-role key between its physical press and release. A key
+// auto-repeats (still `pressed == true`) at the evdev layer while held, so
+// this has to distinguish "still waiting to find out" from "already
+// resolved" to avoid restarting the pending/timeout cycle on every repeat.
+enum DualRoleState {
+    Pending { generation: u64 },
+    Held,
+}
+
+/// Sits between reading a physical `Key` and writing its remapped form,
+/// applying simple substitutions and dual-role tap/hold resolution.
+pub struct Remapper<F: Fn(HoldTimeout) + Send + Sync + 'static> {
+    simple: HashMap<u16, u16>,
+    dual_role: HashMap<u16, DualRole>,
+    state: HashMap<u16, DualRoleState>,
+    next_generation: u64,
+    on_timeout: std::sync::Arc<F>,
+}
+
+impl<F: Fn(HoldTimeout) + Send + Sync + 'static> Remapper<F> {
+    pub fn new(config: &RemapConfig, on_timeout: F) -> Self {
+        let mut simple = HashMap::new();
+        let mut dual_role = HashMap::new();
+
+        for entry in &config.entries {
+            match *entry {
+                RemapEntry::Simple { from, to } => {
+                    simple.insert(from, to);
+                }
+                RemapEntry::DualRole {
+                    from,
+                    tap,
+                    hold,
+                    threshold_ms,
+                } => {
+                    dual_role.insert(
+                        from,
+                        DualRole {
+                            tap,
+                            hold,
+                            threshold_ms,
+                        },
+                    );
+                }
+            }
+        }
+
+        Self {
+            simple,
+            dual_role,
+            state: HashMap::new(),
+            next_generation: 0,
+            on_timeout: std::sync::Arc::new(on_timeout),
+        }
+    }
+
+    /// Feeds a physical key event through the remap layer, calling `emit`
+    /// with zero or more keys to actually write out.
+    pub fn on_key(&mut self, key: Key, pressed: bool, mut emit: impl FnMut(Key, bool)) {
+        let Key(code) = key;
+
+        if let Some(&DualRole {
+            tap,
+            hold,
+            threshold_ms,
+        }) = self.dual_role.get(&code)
+        {
+            if pressed {
+                if self.state.contains_key(&code) {
+                    // Auto-repeat of a key that's already pending or
+                    // already resolved as a hold: not a fresh press, so
+                    // don't restart the pending/timeout cycle.
+                    return;
+                }
+
+                // A second dual-role key pressed while the first's hold
+                // timer is still pending counts as "another key pressed
+                // before the threshold": resolve the first as a hold too.
+                self.resolve_pending_as_holds(Some(code), &mut emit);
+
+                self.next_generation += 1;
+                let generation = self.next_generation;
+                self.state.insert(code, DualRoleState::Pending { generation });
+
+                let on_timeout = self.on_timeout.clone();
+                thread::spawn(move || {
+                    thread::sleep(Duration::from_millis(threshold_ms));
+                    on_timeout(HoldTimeout { from: code, generation });
+                });
+            } else {
+                match self.state.remove(&code) {
+                    Some(DualRoleState::Pending { .. }) => {
+                        // Released within the threshold with nothing
+                        // resolving it as a hold in between: it was a tap.
+                        emit(Key(tap), true);
+                        emit(Key(tap), false);
+                    }
+                    Some(DualRoleState::Held) => {
+                        // Already resolved as a hold; release the modifier.
+                        emit(Key(hold), false);
+                    }
+                    None => {}
+                }
+            }
+            return;
+        }
+
+        if pressed {
+            self.resolve_pending_as_holds(None, &mut emit);
+        }
+
+        let code = self.simple.get(&code).copied().unwrap_or(code);
+        emit(Key(code), pressed);
+    }
+
+    /// Called when a `HoldTimeout` fires. A no-op if the dual-role key was
+    /// already resolved (tapped, or resolved as a hold by another keypress)
+    /// since the timer was started.
+    pub fn on_hold_timeout(&mut self, timeout: HoldTimeout, mut emit: impl FnMut(Key, bool)) {
+        let still_pending = matches!(
+            self.state.get(&timeout.from),
+            Some(DualRoleState::Pending { generation }) if *generation == timeout.generation
+        );
+        if !still_pending {
+            return;
+        }
+
+        self.state.insert(timeout.from, DualRoleState::Held);
+        if let Some(role) = self.dual_role.get(&timeout.from) {
+            emit(Key(role.hold), true);
+        }
+    }
+
+    fn resolve_pending_as_holds(&mut self, except: Option<u16>, emit: &mut impl FnMut(Key, bool)) {
+        let pending_codes: Vec<u16> = self
+            .state
+            .iter()
+            .filter(|(code, state)| {
+                Some(**code) != except && matches!(state, DualRoleState::Pending { .. })
+            })
+            .map(|(code, _)| *code)
+            .collect();
+        for code in pending_codes {
+            self.state.insert(code, DualRoleState::Held);
+            if let Some(role) = self.dual_role.get(&code) {
+                emit(Key(role.hold), true);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_with(entries: Vec<RemapEntry>) -> RemapConfig {
+        RemapConfig { entries }
+    }
+
+    fn dual_role(from: u16, tap: u16, hold: u16) -> RemapEntry {
+        RemapEntry::DualRole {
+            from,
+            tap,
+            hold,
+            threshold_ms: 50,
+        }
+    }
+
+    #[test]
+    fn tap_emits_tap_key() {
+        let config = config_with(vec![dual_role(1, 10, 11)]);
+        let mut remapper = Remapper::new(&config, |_| {});
+        let mut emitted = Vec::new();
+
+        remapper.on_key(Key(1), true, |k, p| emitted.push((k.0, p)));
+        remapper.on_key(Key(1), false, |k, p| emitted.push((k.0, p)));
+
+        assert_eq!(emitted, vec![(10, true), (10, false)]);
+    }
+
+    #[test]
+    fn hold_by_timeout_emits_hold_key() {
+        let config = config_with(vec![dual_role(1, 10, 11)]);
+        let mut remapper = Remapper::new(&config, |_| {});
+        let mut emitted = Vec::new();
+
+        remapper.on_key(Key(1), true, |k, p| emitted.push((k.0, p)));
+        // Simulate the hold timer firing before release, instead of waiting
+        // out the real threshold.
+        remapper.on_hold_timeout(
+            HoldTimeout {
+                from: 1,
+                generation: 1,
+            },
+            |k, p| emitted.push((k.0, p)),
+        );
+        remapper.on_key(Key(1), false, |k, p| emitted.push((k.0, p)));
+
+        assert_eq!(emitted, vec![(11, true), (11, false)]);
+    }
+
+    #[test]
+    fn hold_by_other_keypress_emits_hold_key() {
+        let config = config_with(vec![dual_role(1, 10, 11)]);
+        let mut remapper = Remapper::new(&config, |_| {});
+        let mut emitted = Vec::new();
+
+        remapper.on_key(Key(1), true, |k, p| emitted.push((k.0, p)));
+        remapper.on_key(Key(2), true, |k, p| emitted.push((k.0, p)));
+
+        assert_eq!(emitted, vec![(11, true), (2, true)]);
+    }
+
+    #[test]
+    fn hold_by_second_dual_role_keypress_emits_hold_key() {
+        let config = config_with(vec![dual_role(1, 10, 11), dual_role(2, 20, 21)]);
+        let mut remapper = Remapper::new(&config, |_| {});
+        let mut emitted = Vec::new();
+
+        remapper.on_key(Key(1), true, |k, p| emitted.push((k.0, p)));
+        remapper.on_key(Key(2), true, |k, p| emitted.push((k.0, p)));
+        // Key 1 is resolved as a hold; key 2 becomes pending in its place.
+        assert_eq!(emitted, vec![(11, true)]);
+
+        // Key 2 is released within the threshold, with nothing else having
+        // resolved it as a hold, so it's still a tap.
+        remapper.on_key(Key(2), false, |k, p| emitted.push((k.0, p)));
+        assert_eq!(emitted, vec![(11, true), (20, true), (20, false)]);
+    }
+
+    #[test]
+    fn repeat_press_while_pending_is_a_no_op() {
+        let config = config_with(vec![dual_role(1, 10, 11)]);
+        let mut remapper = Remapper::new(&config, |_| {});
+        let mut emitted = Vec::new();
+
+        remapper.on_key(Key(1), true, |k, p| emitted.push((k.0, p)));
+        // Kernel auto-repeat: another keydown for the same code before it's
+        // released or resolved as a hold.
+        remapper.on_key(Key(1), true, |k, p| emitted.push((k.0, p)));
+        assert!(emitted.is_empty());
+
+        // Still resolves as a tap on release, since nothing pre-empted it.
+        remapper.on_key(Key(1), false, |k, p| emitted.push((k.0, p)));
+        assert_eq!(emitted, vec![(10, true), (10, false)]);
+    }
+
+    #[test]
+    fn repeat_press_while_held_is_a_no_op() {
+        let config = config_with(vec![dual_role(1, 10, 11)]);
+        let mut remapper = Remapper::new(&config, |_| {});
+        let mut emitted = Vec::new();
+
+        remapper.on_key(Key(1), true, |k, p| emitted.push((k.0, p)));
+        remapper.on_hold_timeout(
+            HoldTimeout {
+                from: 1,
+                generation: 1,
+            },
+            |k, p| emitted.push((k.0, p)),
+        );
+        assert_eq!(emitted, vec![(11, true)]);
+
+        // Auto-repeat while already resolved as a hold must not restart the
+        // pending/timeout cycle or re-emit the hold key.
+        remapper.on_key(Key(1), true, |k, p| emitted.push((k.0, p)));
+        assert_eq!(emitted, vec![(11, true)]);
+
+        remapper.on_key(Key(1), false, |k, p| emitted.push((k.0, p)));
+        assert_eq!(emitted, vec![(11, true), (11, false)]);
+    }
+}